@@ -16,12 +16,52 @@
 use crate::prelude::*;
 use std::ffi::CString;
 use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::c_void;
 
 // -----------------------------------------------------------------------------
 // CeedQFunctionContext context wrapper
 // -----------------------------------------------------------------------------
 pub struct QFunctionContext {
     pub(crate) ptr: bind_ceed::CeedQFunctionContext,
+    /// `num_values` recorded at `register_double`/`register_int32` time, keyed
+    /// by field name, so `set_double`/`set_int32` can validate slice lengths
+    field_num_values: std::collections::HashMap<String, usize>,
+}
+
+// -----------------------------------------------------------------------------
+// QFunctionContextDataMut: RAII guard for borrowed, typed context data
+// -----------------------------------------------------------------------------
+/// A mutable, typed view of the data owned by a `QFunctionContext`.
+///
+/// The backend-owned buffer is exposed directly (no copy is made), so large
+/// context structs are not duplicated on every access. `CeedQFunctionContextRestoreData`
+/// is called automatically when the guard is dropped.
+pub struct QFunctionContextDataMut<'a, T> {
+    ptr: bind_ceed::CeedQFunctionContext,
+    data: &'a mut T,
+}
+
+impl<'a, T> Deref for QFunctionContextDataMut<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.data
+    }
+}
+
+impl<'a, T> DerefMut for QFunctionContextDataMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.data
+    }
+}
+
+impl<'a, T> Drop for QFunctionContextDataMut<'a, T> {
+    fn drop(&mut self) {
+        let mut ptr = self.data as *mut T as *mut c_void;
+        unsafe {
+            bind_ceed::CeedQFunctionContextRestoreData(self.ptr, &mut ptr);
+        }
+    }
 }
 
 // -----------------------------------------------------------------------------
@@ -61,7 +101,531 @@ impl QFunctionContext {
     pub fn create(ceed: & crate::Ceed) -> Self {
         let mut ptr = std::ptr::null_mut();
         unsafe { bind_ceed::CeedQFunctionContextCreate(ceed.ptr, &mut ptr) };
-        Self { ptr }
+        Self {
+            ptr,
+            field_num_values: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Set the data used by a QFunctionContext, freeing any previously set data
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure `data` is valid for `size_of::<T>()` bytes on
+    /// `mem_type` for as long as `copy_mode` requires:
+    /// * `CopyMode::CopyValues` - libCEED copies the values immediately; `data`
+    ///   need only be valid for the duration of this call.
+    /// * `CopyMode::UsePointer` - libCEED stores the raw pointer and does not
+    ///   free it; `data` must remain valid for as long as the context may use it.
+    /// * `CopyMode::OwnPointer` - libCEED takes ownership of `data` and will
+    ///   `free()` it; `data` must have come from an allocation `free()` can
+    ///   release (see `set_data_owned` for a safe wrapper around this mode).
+    pub unsafe fn set_data<T>(
+        &mut self,
+        mem_type: MemType,
+        copy_mode: CopyMode,
+        data: *mut T,
+    ) -> crate::Result<()> {
+        let ierr = bind_ceed::CeedQFunctionContextSetData(
+            self.ptr,
+            mem_type.into(),
+            copy_mode.into(),
+            std::mem::size_of::<T>(),
+            data as *mut c_void,
+        );
+        if ierr != 0 {
+            return Err(format!(
+                "libCEED failed to set context data (error code {})",
+                ierr
+            ));
+        }
+        Ok(())
+    }
+
+    /// Set the data used by a QFunctionContext to an owned heap allocation,
+    /// handing ownership to libCEED, which frees it (with C `free()`) when the
+    /// context is destroyed or the data is replaced.
+    ///
+    /// `data` is copied into a fresh `malloc`-backed allocation rather than a
+    /// `Box`, since Rust does not guarantee `Box`'s allocator is the system
+    /// allocator that libCEED's `free()` expects to match.
+    ///
+    /// * `mem_type` - Memory type of the data being passed
+    /// * `data`     - Data to hand off to libCEED
+    ///
+    /// ```
+    /// # use libceed::prelude::*;
+    /// # fn main() -> libceed::Result<()> {
+    /// let ceed = libceed::Ceed::init("/cpu/self/ref/serial");
+    /// let mut ctx = ceed.q_function_context();
+    ///
+    /// #[repr(C)]
+    /// struct Params {
+    ///     dt: f64,
+    /// }
+    /// ctx.set_data_owned(MemType::Host, Params { dt: 1e-3 })?;
+    /// unsafe {
+    ///     let view = ctx.data_mut::<Params>(MemType::Host)?;
+    ///     assert_eq!(view.dt, 1e-3);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_data_owned<T>(&mut self, mem_type: MemType, data: T) -> crate::Result<()> {
+        unsafe {
+            let raw = bind_ceed::malloc(std::mem::size_of::<T>()) as *mut T;
+            assert!(
+                !raw.is_null(),
+                "malloc failed while allocating QFunctionContext data"
+            );
+            raw.write(data);
+            self.set_data(mem_type, CopyMode::OwnPointer, raw)
+        }
+    }
+
+    /// Get read/write access to the data of a QFunctionContext via a RAII guard
+    /// that restores the context when it is dropped
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `T` matches the type and layout of the data
+    /// currently stored on `mem_type` (no larger than `data_size()` bytes); a mismatch
+    /// is undefined behavior.
+    ///
+    /// * `mem_type` - Memory type on which to access the data
+    pub unsafe fn data_mut<T>(
+        &mut self,
+        mem_type: MemType,
+    ) -> crate::Result<QFunctionContextDataMut<T>> {
+        let mut ptr: *mut c_void = std::ptr::null_mut();
+        let ierr = bind_ceed::CeedQFunctionContextGetData(self.ptr, mem_type.into(), &mut ptr);
+        if ierr != 0 || ptr.is_null() {
+            return Err(format!(
+                "libCEED failed to get context data for the requested memory type (error code {})",
+                ierr
+            ));
+        }
+        let data = &mut *(ptr as *mut T);
+        Ok(QFunctionContextDataMut {
+            ptr: self.ptr,
+            data,
+        })
+    }
+
+    /// Register a `f64` field in a QFunctionContext's data that can be set
+    /// independently of the rest of the context's data by label
+    ///
+    /// * `field_name` - Name of field to register
+    /// * `offset`     - Offset, in bytes, of the field within the context's data
+    /// * `num_values` - Number of values in the field
+    /// * `description` - Human readable description of the field
+    ///
+    /// ```
+    /// # use libceed::prelude::*;
+    /// # fn main() -> libceed::Result<()> {
+    /// let ceed = libceed::Ceed::init("/cpu/self/ref/serial");
+    /// let mut ctx = ceed.q_function_context();
+    /// ctx.set_data_owned(MemType::Host, 1e-3f64)?;
+    /// ctx.register_double("time step", 0, 1, "time step size, in seconds")?;
+    /// let label = ctx.get_field_label("time step")?;
+    /// ctx.set_double(&label, &[2e-3])?;
+    /// unsafe {
+    ///     assert_eq!(*ctx.data_mut::<f64>(MemType::Host)?, 2e-3);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_double(
+        &mut self,
+        field_name: &str,
+        offset: usize,
+        num_values: usize,
+        description: &str,
+    ) -> crate::Result<()> {
+        let field_name_c =
+            CString::new(field_name).map_err(|e| format!("invalid field name: {}", e))?;
+        let description_c =
+            CString::new(description).map_err(|e| format!("invalid field description: {}", e))?;
+        let ierr = unsafe {
+            bind_ceed::CeedQFunctionContextRegisterDouble(
+                self.ptr,
+                field_name_c.as_ptr(),
+                offset,
+                num_values,
+                description_c.as_ptr(),
+            )
+        };
+        if ierr != 0 {
+            return Err(format!(
+                "libCEED failed to register context field `{}` (error code {})",
+                field_name, ierr
+            ));
+        }
+        self.field_num_values
+            .insert(field_name.to_string(), num_values);
+        Ok(())
+    }
+
+    /// Register a `i32` field in a QFunctionContext's data that can be set
+    /// independently of the rest of the context's data by label
+    ///
+    /// * `field_name` - Name of field to register
+    /// * `offset`     - Offset, in bytes, of the field within the context's data
+    /// * `num_values` - Number of values in the field
+    /// * `description` - Human readable description of the field
+    pub fn register_int32(
+        &mut self,
+        field_name: &str,
+        offset: usize,
+        num_values: usize,
+        description: &str,
+    ) -> crate::Result<()> {
+        let field_name_c =
+            CString::new(field_name).map_err(|e| format!("invalid field name: {}", e))?;
+        let description_c =
+            CString::new(description).map_err(|e| format!("invalid field description: {}", e))?;
+        let ierr = unsafe {
+            bind_ceed::CeedQFunctionContextRegisterInt32(
+                self.ptr,
+                field_name_c.as_ptr(),
+                offset,
+                num_values,
+                description_c.as_ptr(),
+            )
+        };
+        if ierr != 0 {
+            return Err(format!(
+                "libCEED failed to register context field `{}` (error code {})",
+                field_name, ierr
+            ));
+        }
+        self.field_num_values
+            .insert(field_name.to_string(), num_values);
+        Ok(())
+    }
+
+    /// Get the label corresponding to a registered field name, used to set
+    /// that field's values independently of the rest of the context's data.
+    ///
+    /// The returned label is an owned handle, independent of any borrow of
+    /// `self`, so it can be held across later `set_double`/`set_int32` calls.
+    ///
+    /// * `field_name` - Name of the field to look up
+    ///
+    /// ```
+    /// # use libceed::prelude::*;
+    /// # fn main() -> libceed::Result<()> {
+    /// let ceed = libceed::Ceed::init("/cpu/self/ref/serial");
+    /// let mut ctx = ceed.q_function_context();
+    /// ctx.set_data_owned(MemType::Host, 1e-3f64)?;
+    /// ctx.register_double("time step", 0, 1, "time step size, in seconds")?;
+    /// let label = ctx.get_field_label("time step")?;
+    /// ctx.set_double(&label, &[2e-3])?;
+    /// unsafe {
+    ///     assert_eq!(*ctx.data_mut::<f64>(MemType::Host)?, 2e-3);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_field_label(&self, field_name: &str) -> crate::Result<ContextFieldLabel> {
+        let num_values = *self
+            .field_num_values
+            .get(field_name)
+            .ok_or_else(|| format!("no context field registered with name `{}`", field_name))?;
+        let field_name_c =
+            CString::new(field_name).map_err(|e| format!("invalid field name: {}", e))?;
+        let mut label = std::ptr::null();
+        let ierr = unsafe {
+            bind_ceed::CeedQFunctionContextGetFieldLabel(self.ptr, field_name_c.as_ptr(), &mut label)
+        };
+        if ierr != 0 || label.is_null() {
+            return Err(format!(
+                "libCEED failed to resolve a label for context field `{}`",
+                field_name
+            ));
+        }
+        Ok(ContextFieldLabel {
+            ptr: label,
+            num_values,
+        })
+    }
+
+    /// Set the `f64` values of a previously registered field, by label
+    ///
+    /// * `label`  - Label of the field to set, from `get_field_label`
+    /// * `values` - Values to set; must have the same length as `num_values`
+    ///   passed to `register_double` for this field
+    pub fn set_double(&mut self, label: &ContextFieldLabel, values: &[f64]) -> crate::Result<()> {
+        if values.len() != label.num_values {
+            return Err(format!(
+                "context field expects {} values, found {}",
+                label.num_values,
+                values.len()
+            ));
+        }
+        let ierr =
+            unsafe { bind_ceed::CeedQFunctionContextSetDouble(self.ptr, label.ptr, values.as_ptr()) };
+        if ierr != 0 {
+            return Err(format!(
+                "libCEED failed to set context field values (error code {})",
+                ierr
+            ));
+        }
+        Ok(())
+    }
+
+    /// Set the `i32` values of a previously registered field, by label
+    ///
+    /// * `label`  - Label of the field to set, from `get_field_label`
+    /// * `values` - Values to set; must have the same length as `num_values`
+    ///   passed to `register_int32` for this field
+    pub fn set_int32(&mut self, label: &ContextFieldLabel, values: &[i32]) -> crate::Result<()> {
+        if values.len() != label.num_values {
+            return Err(format!(
+                "context field expects {} values, found {}",
+                label.num_values,
+                values.len()
+            ));
+        }
+        let ierr =
+            unsafe { bind_ceed::CeedQFunctionContextSetInt32(self.ptr, label.ptr, values.as_ptr()) };
+        if ierr != 0 {
+            return Err(format!(
+                "libCEED failed to set context field values (error code {})",
+                ierr
+            ));
+        }
+        Ok(())
+    }
+
+    /// Take ownership of the data of a QFunctionContext and remove it from the context
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `T` matches the type and layout of the data
+    /// currently stored on `mem_type` (no larger than `data_size()` bytes); a mismatch
+    /// is undefined behavior. The caller becomes responsible for freeing the
+    /// returned pointer.
+    ///
+    /// * `mem_type` - Memory type requested for the data
+    ///
+    /// ```
+    /// # use libceed::prelude::*;
+    /// # fn main() -> libceed::Result<()> {
+    /// let ceed = libceed::Ceed::init("/cpu/self/ref/serial");
+    /// let mut ctx = ceed.q_function_context();
+    /// ctx.set_data_owned(MemType::Host, [1.0f64; 3])?;
+    /// unsafe {
+    ///     let reclaimed = ctx.take_data::<[f64; 3]>(MemType::Host)?;
+    ///     assert_eq!(*reclaimed, [1.0, 1.0, 1.0]);
+    ///     // `reclaimed` was allocated with `malloc` by `set_data_owned`; free
+    ///     // it with a matching deallocator rather than `Box::from_raw`.
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub unsafe fn take_data<T>(&mut self, mem_type: MemType) -> crate::Result<*mut T> {
+        let mut ptr: *mut c_void = std::ptr::null_mut();
+        let ierr = bind_ceed::CeedQFunctionContextTakeData(self.ptr, mem_type.into(), &mut ptr);
+        if ierr != 0 || ptr.is_null() {
+            return Err(format!(
+                "libCEED failed to take context data for the requested memory type (error code {})",
+                ierr
+            ));
+        }
+        Ok(ptr as *mut T)
+    }
+
+    /// Get the size, in bytes, of a QFunctionContext's data
+    pub fn data_size(&self) -> crate::Result<usize> {
+        let mut size = 0;
+        let ierr = unsafe { bind_ceed::CeedQFunctionContextGetContextSize(self.ptr, &mut size) };
+        if ierr != 0 {
+            return Err(format!(
+                "libCEED failed to get context data size (error code {})",
+                ierr
+            ));
+        }
+        Ok(size)
+    }
+
+    /// Register a callback to be run by libCEED when it frees the data of a
+    /// QFunctionContext, so that a Rust-side destructor runs on the given
+    /// memory type
+    ///
+    /// * `mem_type` - Memory type on which the destructor callback operates
+    /// * `f`        - Destructor callback
+    pub fn set_data_destroy(
+        &mut self,
+        mem_type: MemType,
+        f: unsafe extern "C" fn(*mut c_void) -> i32,
+    ) -> crate::Result<()> {
+        let ierr = unsafe {
+            bind_ceed::CeedQFunctionContextSetDataDestroy(self.ptr, mem_type.into(), Some(f))
+        };
+        if ierr != 0 {
+            return Err(format!(
+                "libCEED failed to register the context data destroy callback (error code {})",
+                ierr
+            ));
+        }
+        Ok(())
+    }
+
+    /// Run a closure against the typed data of a QFunctionContext, restoring
+    /// the context when the closure returns or panics
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `T` matches the type and layout of the data
+    /// currently stored on `mem_type` (no larger than `data_size()` bytes); a mismatch
+    /// is undefined behavior. `RestoreData` is still guaranteed to run even if
+    /// `f` panics, via the `QFunctionContextDataMut` drop guard.
+    ///
+    /// * `mem_type` - Memory type on which to access the data
+    /// * `f`        - Closure to run against the data
+    ///
+    /// ```
+    /// # use libceed::prelude::*;
+    /// # fn main() -> libceed::Result<()> {
+    /// let ceed = libceed::Ceed::init("/cpu/self/ref/serial");
+    /// let mut ctx = ceed.q_function_context();
+    /// ctx.set_data_owned(MemType::Host, 1.0e-3f64)?;
+    /// unsafe {
+    ///     ctx.with_data_mut::<f64, _>(MemType::Host, |dt| *dt *= 2.0)?;
+    ///     assert_eq!(*ctx.data_mut::<f64>(MemType::Host)?, 2.0e-3);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub unsafe fn with_data_mut<T, F: FnOnce(&mut T)>(
+        &mut self,
+        mem_type: MemType,
+        f: F,
+    ) -> crate::Result<()> {
+        let mut guard = self.data_mut::<T>(mem_type)?;
+        f(&mut guard);
+        Ok(())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// ContextFieldLabel
+// -----------------------------------------------------------------------------
+/// A lightweight, owned handle to a field previously registered on a
+/// QFunctionContext, used to update that field's values without touching the
+/// rest of the context's data. Not tied to a borrow of the context, so it can
+/// be obtained once and reused across later `set_double`/`set_int32` calls.
+pub struct ContextFieldLabel {
+    ptr: bind_ceed::CeedContextFieldLabel,
+    /// `num_values` the field was registered with, used to validate slices
+    /// passed to `set_double`/`set_int32`
+    num_values: usize,
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    struct Params {
+        time_step: f64,
+        count: i32,
+    }
+
+    #[test]
+    fn set_data_owned_and_data_mut_round_trip() -> crate::Result<()> {
+        let ceed = crate::Ceed::init("/cpu/self/ref/serial");
+        let mut ctx = ceed.q_function_context();
+        ctx.set_data_owned(
+            MemType::Host,
+            Params {
+                time_step: 1e-3,
+                count: 4,
+            },
+        )?;
+        unsafe {
+            let view = ctx.data_mut::<Params>(MemType::Host)?;
+            assert_eq!(view.time_step, 1e-3);
+            assert_eq!(view.count, 4);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn register_get_label_set_double_round_trip() -> crate::Result<()> {
+        let ceed = crate::Ceed::init("/cpu/self/ref/serial");
+        let mut ctx = ceed.q_function_context();
+        ctx.set_data_owned(
+            MemType::Host,
+            Params {
+                time_step: 1e-3,
+                count: 4,
+            },
+        )?;
+        ctx.register_double("time step", 0, 1, "time step size, in seconds")?;
+        let label = ctx.get_field_label("time step")?;
+        ctx.set_double(&label, &[2e-3])?;
+        unsafe {
+            let view = ctx.data_mut::<Params>(MemType::Host)?;
+            assert_eq!(view.time_step, 2e-3);
+            assert_eq!(view.count, 4);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn set_double_rejects_mismatched_length() -> crate::Result<()> {
+        let ceed = crate::Ceed::init("/cpu/self/ref/serial");
+        let mut ctx = ceed.q_function_context();
+        ctx.set_data_owned(
+            MemType::Host,
+            Params {
+                time_step: 1e-3,
+                count: 4,
+            },
+        )?;
+        ctx.register_double("time step", 0, 1, "time step size, in seconds")?;
+        let label = ctx.get_field_label("time step")?;
+        assert!(ctx.set_double(&label, &[1.0, 2.0]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn get_field_label_rejects_unregistered_name() -> crate::Result<()> {
+        let ceed = crate::Ceed::init("/cpu/self/ref/serial");
+        let mut ctx = ceed.q_function_context();
+        ctx.set_data_owned(MemType::Host, 1e-3f64)?;
+        assert!(ctx.get_field_label("does not exist").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn take_data_reclaims_values() -> crate::Result<()> {
+        let ceed = crate::Ceed::init("/cpu/self/ref/serial");
+        let mut ctx = ceed.q_function_context();
+        ctx.set_data_owned(MemType::Host, [1.0f64, 2.0, 3.0])?;
+        unsafe {
+            let reclaimed = ctx.take_data::<[f64; 3]>(MemType::Host)?;
+            assert_eq!(*reclaimed, [1.0, 2.0, 3.0]);
+            bind_ceed::free(reclaimed as *mut c_void);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn with_data_mut_applies_closure_and_restores() -> crate::Result<()> {
+        let ceed = crate::Ceed::init("/cpu/self/ref/serial");
+        let mut ctx = ceed.q_function_context();
+        ctx.set_data_owned(MemType::Host, 1.0e-3f64)?;
+        unsafe {
+            ctx.with_data_mut::<f64, _>(MemType::Host, |dt| *dt *= 2.0)?;
+            assert_eq!(*ctx.data_mut::<f64>(MemType::Host)?, 2.0e-3);
+        }
+        Ok(())
     }
 }
 